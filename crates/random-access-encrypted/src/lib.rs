@@ -0,0 +1,568 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::Aes256Gcm;
+use anyhow::{anyhow, Error};
+use async_trait::async_trait;
+use chacha20poly1305::ChaCha20Poly1305;
+use rand::RngCore;
+use random_access_storage::RandomAccess;
+
+/// Default logical encryption block size, in bytes.
+pub const DEFAULT_BLOCK_SIZE: u64 = 4096;
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+/// Per-frame header: nonce || tag || flags || ciphertext length.
+const HEADER_LEN: usize = NONCE_LEN + TAG_LEN + 1 + 4;
+const FLAG_COMPRESSED: u8 = 0b0000_0001;
+
+/// AEAD used to seal each block at rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionType {
+    /// AES-256 in Galois/Counter mode.
+    Aes256Gcm,
+    /// ChaCha20-Poly1305.
+    ChaCha20Poly1305,
+}
+
+/// Random access storage that compresses then encrypts every block of an
+/// inner backend.
+///
+/// The logical byte space is partitioned into fixed-size blocks of `B` bytes
+/// (set on the builder, [`DEFAULT_BLOCK_SIZE`] by default); block `i` covers logical bytes `[i*B, (i+1)*B)` and is stored in a
+/// fixed-size physical frame carrying its own nonce and AEAD tag. Frame `0`
+/// is a header holding the logical length, so block `i` lives at physical
+/// offset `(i + 1) * frame_len`.
+#[derive(Debug)]
+pub struct EncryptedStorage<T>
+where
+    T: RandomAccess + std::fmt::Debug,
+{
+    inner: T,
+    cipher: EncryptionType,
+    key: [u8; 32],
+    block_size: u64,
+    frame_len: u64,
+    length: u64,
+    compress: bool,
+    auto_sync: bool,
+}
+
+impl<T> EncryptedStorage<T>
+where
+    T: RandomAccess<Error = Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug + Send,
+{
+    pub fn builder(inner: T, key: [u8; 32]) -> EncryptedBuilder<T> {
+        EncryptedBuilder::new(inner, key)
+    }
+
+    fn frame_offset(&self, index: u64) -> u64 {
+        (index + 1) * self.frame_len
+    }
+
+    /// Compress (optionally) and seal `plaintext` into a frame payload.
+    fn seal_block(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let (payload, compressed) = if self.compress {
+            let packed = zstd::encode_all(plaintext, 0)?;
+            if packed.len() < plaintext.len() {
+                (packed, true)
+            } else {
+                (plaintext.to_vec(), false)
+            }
+        } else {
+            (plaintext.to_vec(), false)
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let sealed = self.encrypt(&nonce, &payload)?;
+
+        // AEAD output is ciphertext || tag; split the tag into the header.
+        if sealed.len() < TAG_LEN {
+            return Err(anyhow!("AEAD output shorter than tag"));
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+        let clen = ciphertext.len() as u32;
+
+        let mut frame = vec![0u8; self.frame_len as usize];
+        frame[..NONCE_LEN].copy_from_slice(&nonce);
+        frame[NONCE_LEN..NONCE_LEN + TAG_LEN].copy_from_slice(tag);
+        frame[NONCE_LEN + TAG_LEN] = if compressed { FLAG_COMPRESSED } else { 0 };
+        frame[NONCE_LEN + TAG_LEN + 1..HEADER_LEN].copy_from_slice(&clen.to_le_bytes());
+        frame[HEADER_LEN..HEADER_LEN + ciphertext.len()].copy_from_slice(ciphertext);
+        Ok(frame)
+    }
+
+    /// Verify, decrypt and decompress a frame payload back to plaintext.
+    fn open_block(&self, frame: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce: [u8; NONCE_LEN] = frame[..NONCE_LEN]
+            .try_into()
+            .map_err(|_| anyhow!("truncated block frame"))?;
+        let tag = &frame[NONCE_LEN..NONCE_LEN + TAG_LEN];
+        let flags = frame[NONCE_LEN + TAG_LEN];
+        let mut clen_buf = [0u8; 4];
+        clen_buf.copy_from_slice(&frame[NONCE_LEN + TAG_LEN + 1..HEADER_LEN]);
+        let clen = u32::from_le_bytes(clen_buf) as usize;
+
+        let mut sealed = Vec::with_capacity(clen + TAG_LEN);
+        sealed.extend_from_slice(&frame[HEADER_LEN..HEADER_LEN + clen]);
+        sealed.extend_from_slice(tag);
+
+        // Tag verification lives inside `decrypt`; a mismatch is an error, we
+        // never hand back unverified bytes.
+        let payload = self.decrypt(&nonce, &sealed)?;
+        if flags & FLAG_COMPRESSED != 0 {
+            Ok(zstd::decode_all(&payload[..])?)
+        } else {
+            Ok(payload)
+        }
+    }
+
+    fn encrypt(&self, nonce: &[u8; NONCE_LEN], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let payload = Payload {
+            msg: plaintext,
+            aad: &[],
+        };
+        match self.cipher {
+            EncryptionType::Aes256Gcm => Aes256Gcm::new(self.key.as_ref().into())
+                .encrypt(nonce.into(), payload)
+                .map_err(|_| anyhow!("AES-256-GCM seal failed")),
+            EncryptionType::ChaCha20Poly1305 => ChaCha20Poly1305::new(self.key.as_ref().into())
+                .encrypt(nonce.into(), payload)
+                .map_err(|_| anyhow!("ChaCha20-Poly1305 seal failed")),
+        }
+    }
+
+    fn decrypt(&self, nonce: &[u8; NONCE_LEN], sealed: &[u8]) -> Result<Vec<u8>, Error> {
+        let payload = Payload {
+            msg: sealed,
+            aad: &[],
+        };
+        match self.cipher {
+            EncryptionType::Aes256Gcm => Aes256Gcm::new(self.key.as_ref().into())
+                .decrypt(nonce.into(), payload)
+                .map_err(|_| anyhow!("AEAD tag verification failed")),
+            EncryptionType::ChaCha20Poly1305 => ChaCha20Poly1305::new(self.key.as_ref().into())
+                .decrypt(nonce.into(), payload)
+                .map_err(|_| anyhow!("AEAD tag verification failed")),
+        }
+    }
+
+    /// Current plaintext length of block `index`, given the logical length.
+    fn block_len(&self, index: u64) -> usize {
+        let start = index * self.block_size;
+        if start >= self.length {
+            0
+        } else {
+            std::cmp::min(self.block_size, self.length - start) as usize
+        }
+    }
+
+    /// Read-modify-write helper: fetch the current plaintext of a block.
+    async fn read_block(&mut self, index: u64) -> Result<Vec<u8>, Error> {
+        let len = self.block_len(index);
+        let mut plaintext = vec![0u8; self.block_size as usize];
+        if len != 0 {
+            let frame = self
+                .inner
+                .read(self.frame_offset(index), self.frame_len)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            let decrypted = self.open_block(&frame)?;
+            plaintext[..decrypted.len()].copy_from_slice(&decrypted);
+        }
+        Ok(plaintext)
+    }
+
+    async fn persist_header(&mut self) -> Result<(), Error> {
+        let mut header = vec![0u8; self.frame_len as usize];
+        header[..8].copy_from_slice(&self.length.to_le_bytes());
+        self.inner
+            .write(0, &header)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+}
+
+#[async_trait]
+impl<T> RandomAccess for EncryptedStorage<T>
+where
+    T: RandomAccess<Error = Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug + Send,
+{
+    type Error = Box<dyn std::error::Error + Sync + Send>;
+
+    async fn write(&mut self, offset: u64, data: &[u8]) -> Result<(), Self::Error> {
+        let end = offset + data.len() as u64;
+        let mut written = 0usize;
+
+        while written < data.len() {
+            let cursor = offset + written as u64;
+            let index = cursor / self.block_size;
+            let block_offset = (cursor % self.block_size) as usize;
+            let take = std::cmp::min(self.block_size as usize - block_offset, data.len() - written);
+
+            let mut plaintext = self.read_block(index).await.map_err(|e| anyhow!(e))?;
+            plaintext[block_offset..block_offset + take]
+                .copy_from_slice(&data[written..written + take]);
+
+            // The valid plaintext length is the high-water mark of this block.
+            let block_start = index * self.block_size;
+            let valid = std::cmp::max(self.block_len(index), block_offset + take);
+            let frame = self.seal_block(&plaintext[..valid]).map_err(|e| anyhow!(e))?;
+            self.inner
+                .write(self.frame_offset(index), &frame)
+                .await?;
+
+            if block_start + valid as u64 > self.length {
+                self.length = block_start + valid as u64;
+            }
+            written += take;
+        }
+
+        if end > self.length {
+            self.length = end;
+        }
+        self.persist_header().await.map_err(|e| anyhow!(e))?;
+        if self.auto_sync {
+            self.inner.sync_all().await?;
+        }
+        Ok(())
+    }
+
+    async fn read(&mut self, offset: u64, length: u64) -> Result<Vec<u8>, Self::Error> {
+        if offset + length > self.length {
+            return Err(anyhow!(
+                "Read bounds exceeded. {} < {}..{}",
+                self.length,
+                offset,
+                offset + length
+            )
+            .into());
+        }
+
+        let mut out = Vec::with_capacity(length as usize);
+        while (out.len() as u64) < length {
+            let cursor = offset + out.len() as u64;
+            let index = cursor / self.block_size;
+            let block_offset = (cursor % self.block_size) as usize;
+            let take = std::cmp::min(
+                self.block_size as usize - block_offset,
+                (length - out.len() as u64) as usize,
+            );
+
+            let plaintext = self.read_block(index).await.map_err(|e| anyhow!(e))?;
+            out.extend_from_slice(&plaintext[block_offset..block_offset + take]);
+        }
+        Ok(out)
+    }
+
+    async fn read_to_writer(
+        &mut self,
+        offset: u64,
+        length: u64,
+        buf: &mut (impl async_std::io::Write + Send),
+    ) -> Result<(), Self::Error> {
+        use async_std::io::prelude::WriteExt;
+
+        if offset + length > self.length {
+            return Err(anyhow!(
+                "Read bounds exceeded. {} < {}..{}",
+                self.length,
+                offset,
+                offset + length
+            )
+            .into());
+        }
+
+        // Decrypt one block at a time and stream the requested slice out, so a
+        // large range never buffers in full.
+        let mut streamed = 0u64;
+        while streamed < length {
+            let cursor = offset + streamed;
+            let index = cursor / self.block_size;
+            let block_offset = (cursor % self.block_size) as usize;
+            let take = std::cmp::min(
+                self.block_size as usize - block_offset,
+                (length - streamed) as usize,
+            );
+
+            let plaintext = self.read_block(index).await.map_err(|e| anyhow!(e))?;
+            buf.write_all(&plaintext[block_offset..block_offset + take]).await?;
+            streamed += take as u64;
+        }
+        buf.flush().await?;
+        Ok(())
+    }
+
+    async fn del(&mut self, offset: u64, length: u64) -> Result<(), Self::Error> {
+        if length == 0 {
+            return Ok(());
+        }
+
+        // Re-seal each touched block with the range zeroed out. We never shrink
+        // the logical length; the bytes simply read back as zero.
+        let mut cleared = 0u64;
+        while cleared < length {
+            let cursor = offset + cleared;
+            let index = cursor / self.block_size;
+            let block_offset = (cursor % self.block_size) as usize;
+            let take = std::cmp::min(
+                self.block_size as usize - block_offset,
+                (length - cleared) as usize,
+            );
+
+            let mut plaintext = self.read_block(index).await.map_err(|e| anyhow!(e))?;
+            for byte in plaintext[block_offset..block_offset + take].iter_mut() {
+                *byte = 0;
+            }
+            let valid = self.block_len(index);
+            let frame = self.seal_block(&plaintext[..valid]).map_err(|e| anyhow!(e))?;
+            self.inner.write(self.frame_offset(index), &frame).await?;
+            cleared += take as u64;
+        }
+
+        if self.auto_sync {
+            self.inner.sync_all().await?;
+        }
+        Ok(())
+    }
+
+    async fn truncate(&mut self, length: u64) -> Result<(), Self::Error> {
+        let blocks = length.div_ceil(self.block_size);
+        self.inner.truncate((blocks + 1) * self.frame_len).await?;
+        self.length = length;
+
+        // Re-seal the now-partial tail block so it carries no stale bytes.
+        if length % self.block_size != 0 {
+            let index = length / self.block_size;
+            let plaintext = self.read_block(index).await.map_err(|e| anyhow!(e))?;
+            let valid = (length % self.block_size) as usize;
+            let frame = self.seal_block(&plaintext[..valid]).map_err(|e| anyhow!(e))?;
+            self.inner.write(self.frame_offset(index), &frame).await?;
+        }
+
+        self.persist_header().await.map_err(|e| anyhow!(e))?;
+        if self.auto_sync {
+            self.inner.sync_all().await?;
+        }
+        Ok(())
+    }
+
+    async fn len(&self) -> Result<u64, Self::Error> {
+        Ok(self.length)
+    }
+
+    async fn is_empty(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.length == 0)
+    }
+
+    async fn sync_all(&mut self) -> Result<(), Self::Error> {
+        self.inner.sync_all().await
+    }
+}
+
+pub struct EncryptedBuilder<T>
+where
+    T: RandomAccess + std::fmt::Debug,
+{
+    inner: T,
+    key: [u8; 32],
+    cipher: EncryptionType,
+    block_size: u64,
+    compress: bool,
+    auto_sync: bool,
+}
+
+impl<T> EncryptedBuilder<T>
+where
+    T: RandomAccess<Error = Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug + Send,
+{
+    pub fn new(inner: T, key: [u8; 32]) -> Self {
+        Self {
+            inner,
+            key,
+            cipher: EncryptionType::ChaCha20Poly1305,
+            block_size: DEFAULT_BLOCK_SIZE,
+            compress: true,
+            auto_sync: true,
+        }
+    }
+
+    /// Select the AEAD used to seal blocks.
+    pub fn cipher(mut self, cipher: EncryptionType) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Set the logical encryption block size.
+    pub fn block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Toggle zstd compression ahead of encryption.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn auto_sync(mut self, auto_sync: bool) -> Self {
+        self.auto_sync = auto_sync;
+        self
+    }
+
+    pub async fn build(self) -> Result<EncryptedStorage<T>, Error> {
+        // Frame capacity must hold the worst-case zstd expansion plus the
+        // per-frame header.
+        let payload_cap = self.block_size + (self.block_size >> 8) + 64;
+        let frame_len = HEADER_LEN as u64 + payload_cap;
+
+        let mut storage = EncryptedStorage {
+            inner: self.inner,
+            cipher: self.cipher,
+            key: self.key,
+            block_size: self.block_size,
+            frame_len,
+            length: 0,
+            compress: self.compress,
+            auto_sync: self.auto_sync,
+        };
+
+        // Recover the logical length from the header frame if present.
+        let inner_len = storage.inner.len().await.map_err(|e| anyhow!(e))?;
+        if inner_len >= frame_len {
+            let header = storage.inner.read(0, frame_len).await.map_err(|e| anyhow!(e))?;
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&header[..8]);
+            storage.length = u64::from_le_bytes(buf);
+        }
+
+        Ok(storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::io::Cursor;
+
+    type BoxErr = Box<dyn std::error::Error + Send + Sync>;
+
+    /// In-memory backend used to exercise the wrapper.
+    #[derive(Debug, Default)]
+    struct MemoryStore {
+        data: Vec<u8>,
+    }
+
+    #[async_trait]
+    impl RandomAccess for MemoryStore {
+        type Error = BoxErr;
+
+        async fn write(&mut self, offset: u64, data: &[u8]) -> Result<(), Self::Error> {
+            let end = (offset as usize) + data.len();
+            if end > self.data.len() {
+                self.data.resize(end, 0);
+            }
+            self.data[offset as usize..end].copy_from_slice(data);
+            Ok(())
+        }
+
+        async fn read(&mut self, offset: u64, length: u64) -> Result<Vec<u8>, Self::Error> {
+            let start = offset as usize;
+            let end = start + length as usize;
+            Ok(self.data[start..end].to_vec())
+        }
+
+        async fn read_to_writer(
+            &mut self,
+            _offset: u64,
+            _length: u64,
+            _buf: &mut (impl async_std::io::Write + Send),
+        ) -> Result<(), Self::Error> {
+            unimplemented!()
+        }
+
+        async fn del(&mut self, _offset: u64, _length: u64) -> Result<(), Self::Error> {
+            unimplemented!()
+        }
+
+        async fn truncate(&mut self, length: u64) -> Result<(), Self::Error> {
+            self.data.resize(length as usize, 0);
+            Ok(())
+        }
+
+        async fn len(&self) -> Result<u64, Self::Error> {
+            Ok(self.data.len() as u64)
+        }
+
+        async fn is_empty(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.data.is_empty())
+        }
+
+        async fn sync_all(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    async fn storage(cipher: EncryptionType, key: [u8; 32]) -> EncryptedStorage<MemoryStore> {
+        EncryptedStorage::builder(MemoryStore::default(), key)
+            .cipher(cipher)
+            .block_size(16)
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[async_std::test]
+    async fn round_trips_across_block_boundaries() {
+        for cipher in [EncryptionType::Aes256Gcm, EncryptionType::ChaCha20Poly1305] {
+            let mut s = storage(cipher, [7u8; 32]).await;
+            // Spans three 16-byte blocks with unaligned start and end.
+            let payload: Vec<u8> = (0..40u8).collect();
+            s.write(5, &payload).await.unwrap();
+            assert_eq!(s.len().await.unwrap(), 45);
+            assert_eq!(s.read(5, 40).await.unwrap(), payload);
+            // A read of an interior slice stays consistent.
+            assert_eq!(s.read(20, 8).await.unwrap(), payload[15..23]);
+        }
+    }
+
+    #[async_std::test]
+    async fn wrong_key_is_rejected_not_returned_as_plaintext() {
+        let mut s = storage(EncryptionType::ChaCha20Poly1305, [1u8; 32]).await;
+        s.write(0, b"secret payload across blocks").await.unwrap();
+
+        // Re-open the same bytes under a different key: tag verification must fail.
+        let inner = MemoryStore {
+            data: s.inner.data.clone(),
+        };
+        let mut other = EncryptedStorage::builder(inner, [2u8; 32])
+            .cipher(EncryptionType::ChaCha20Poly1305)
+            .block_size(16)
+            .build()
+            .await
+            .unwrap();
+        assert!(other.read(0, 8).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn del_zeroes_range_without_shrinking() {
+        let mut s = storage(EncryptionType::Aes256Gcm, [9u8; 32]).await;
+        s.write(0, &[0xAAu8; 40]).await.unwrap();
+        s.del(8, 20).await.unwrap();
+        assert_eq!(s.len().await.unwrap(), 40);
+        let out = s.read(0, 40).await.unwrap();
+        assert!(out[..8].iter().all(|b| *b == 0xAA));
+        assert!(out[8..28].iter().all(|b| *b == 0));
+        assert!(out[28..].iter().all(|b| *b == 0xAA));
+    }
+
+    #[async_std::test]
+    async fn read_to_writer_streams_requested_range() {
+        let mut s = storage(EncryptionType::ChaCha20Poly1305, [3u8; 32]).await;
+        let payload: Vec<u8> = (0..50u8).collect();
+        s.write(0, &payload).await.unwrap();
+        let mut sink = Cursor::new(Vec::new());
+        s.read_to_writer(10, 30, &mut sink).await.unwrap();
+        assert_eq!(sink.into_inner(), payload[10..40]);
+    }
+}
@@ -0,0 +1,488 @@
+use anyhow::{anyhow, Error};
+use async_trait::async_trait;
+pub use lru::LruCache;
+pub use parking_lot::Mutex;
+use random_access_storage::RandomAccess;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+/// Size of a single backing object, in bytes.
+///
+/// The feed's logical byte space is partitioned into fixed-size pages; each
+/// page is stored as one object keyed by its page index.
+pub const PAGE_SIZE: u64 = 64 * 1024;
+
+/// Number of pages kept hot in the read-modify-write cache.
+const DEFAULT_CACHE_PAGES: usize = 64;
+
+/// Minimal async object store the backend persists pages onto.
+///
+/// Only three verbs are needed — fetch, store, drop — so any S3-compatible
+/// client (or an in-memory fake in tests) can back a `Feed<RandomAccessS3>`.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Fetch the object at `key`, or `None` if it does not exist.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Store `data` at `key`, overwriting any previous value.
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), Error>;
+
+    /// Delete the object at `key`; succeeds even if it is already gone.
+    async fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+/// Random access storage backed by a remote object store.
+///
+/// Logical bytes are mapped onto fixed-size [`PAGE_SIZE`] pages stored as
+/// individual objects; `len` is tracked in a small header object so the
+/// length survives across opens without scanning the page space.
+#[derive(Debug)]
+pub struct RandomAccessS3 {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+    length: u64,
+    cache: Mutex<LruCache<u64, Page>>,
+    auto_sync: bool,
+}
+
+impl std::fmt::Debug for dyn ObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObjectStore")
+    }
+}
+
+/// A cached page and whether it carries unflushed writes.
+#[derive(Debug, Clone)]
+struct Page {
+    bytes: Vec<u8>,
+    dirty: bool,
+}
+
+impl RandomAccessS3 {
+    /// Create a new instance.
+    #[allow(clippy::new_ret_no_self)]
+    pub async fn open(
+        store: Arc<dyn ObjectStore>,
+        prefix: impl Into<String>,
+    ) -> Result<RandomAccessS3, Error> {
+        Self::builder(store, prefix).auto_sync(true).build().await
+    }
+
+    pub fn builder(store: Arc<dyn ObjectStore>, prefix: impl Into<String>) -> S3Builder {
+        S3Builder::new(store, prefix)
+    }
+
+    fn header_key(&self) -> String {
+        format!("{}/header", self.prefix)
+    }
+
+    fn page_key(&self, index: u64) -> String {
+        format!("{}/page/{}", self.prefix, index)
+    }
+
+    /// Load a page into the cache, fetching it from the store on a miss.
+    ///
+    /// Pages past the current length (or absent in the store) read back as
+    /// zero-filled, so a read-modify-write of a freshly grown region behaves
+    /// like a sparse local file.
+    async fn load_page(&mut self, index: u64) -> Result<Page, Error> {
+        if let Some(page) = self.cache.lock().get(&index).cloned() {
+            return Ok(page);
+        }
+
+        let bytes = match self.store.get(&self.page_key(index)).await? {
+            Some(mut bytes) => {
+                bytes.resize(PAGE_SIZE as usize, 0);
+                bytes
+            }
+            None => vec![0; PAGE_SIZE as usize],
+        };
+        let page = Page {
+            bytes,
+            dirty: false,
+        };
+        self.cache_put(index, page.clone()).await?;
+        Ok(page)
+    }
+
+    /// Insert a page into the cache, writing back any dirty page it evicts.
+    ///
+    /// `LruCache::push` silently drops the least-recently-used entry when the
+    /// cache is full; if that entry still holds unflushed bytes (possible with
+    /// `auto_sync` off) we must persist it first or the write is lost.
+    async fn cache_put(&self, index: u64, page: Page) -> Result<(), Error> {
+        let evicted = self.cache.lock().push(index, page);
+        if let Some((evicted_index, evicted_page)) = evicted {
+            if evicted_index != index && evicted_page.dirty {
+                self.store
+                    .put(&self.page_key(evicted_index), &evicted_page.bytes)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Persist a dirty page and mark it clean in the cache.
+    async fn flush_page(&self, index: u64, page: &[u8]) -> Result<(), Error> {
+        self.store.put(&self.page_key(index), page).await?;
+        if let Some(cached) = self.cache.lock().get_mut(&index) {
+            cached.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Persist the length header.
+    async fn write_header(&self) -> Result<(), Error> {
+        self.store
+            .put(&self.header_key(), &self.length.to_le_bytes())
+            .await
+    }
+}
+
+#[async_trait]
+impl RandomAccess for RandomAccessS3 {
+    type Error = Box<dyn std::error::Error + Sync + Send>;
+
+    async fn write(&mut self, offset: u64, data: &[u8]) -> Result<(), Self::Error> {
+        let end = offset + data.len() as u64;
+        let mut written = 0usize;
+        let mut cursor = offset;
+
+        while written < data.len() {
+            let index = cursor / PAGE_SIZE;
+            let page_offset = (cursor % PAGE_SIZE) as usize;
+            let take = std::cmp::min(PAGE_SIZE as usize - page_offset, data.len() - written);
+
+            let mut page = self.load_page(index).await?;
+            page.bytes[page_offset..page_offset + take]
+                .copy_from_slice(&data[written..written + take]);
+            page.dirty = true;
+            self.cache_put(index, page.clone()).await?;
+
+            if self.auto_sync {
+                self.flush_page(index, &page.bytes).await?;
+            }
+
+            written += take;
+            cursor += take as u64;
+        }
+
+        if end > self.length {
+            self.length = end;
+            if self.auto_sync {
+                self.write_header().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn read(&mut self, offset: u64, length: u64) -> Result<Vec<u8>, Self::Error> {
+        if offset + length > self.length {
+            return Err(anyhow!(
+                "Read bounds exceeded. {} < {}..{}",
+                self.length,
+                offset,
+                offset + length
+            )
+            .into());
+        }
+
+        let mut out = Vec::with_capacity(length as usize);
+        let mut cursor = offset;
+
+        while (out.len() as u64) < length {
+            let index = cursor / PAGE_SIZE;
+            let page_offset = (cursor % PAGE_SIZE) as usize;
+            let take = std::cmp::min(
+                PAGE_SIZE as usize - page_offset,
+                (length - out.len() as u64) as usize,
+            );
+
+            let page = self.load_page(index).await?;
+            out.extend_from_slice(&page.bytes[page_offset..page_offset + take]);
+            cursor += take as u64;
+        }
+
+        Ok(out)
+    }
+
+    async fn read_to_writer(
+        &mut self,
+        offset: u64,
+        length: u64,
+        buf: &mut (impl async_std::io::Write + Send),
+    ) -> Result<(), Self::Error> {
+        use async_std::io::prelude::WriteExt;
+
+        if offset + length > self.length {
+            return Err(anyhow!(
+                "Read bounds exceeded. {} < {}..{}",
+                self.length,
+                offset,
+                offset + length
+            )
+            .into());
+        }
+
+        // Stream a page's worth at a time so a large range never buffers whole.
+        let mut streamed = 0u64;
+        while streamed < length {
+            let cursor = offset + streamed;
+            let index = cursor / PAGE_SIZE;
+            let page_offset = (cursor % PAGE_SIZE) as usize;
+            let take = std::cmp::min(
+                PAGE_SIZE as usize - page_offset,
+                (length - streamed) as usize,
+            );
+
+            let page = self.load_page(index).await?;
+            buf.write_all(&page.bytes[page_offset..page_offset + take]).await?;
+            streamed += take as u64;
+        }
+        buf.flush().await?;
+        Ok(())
+    }
+
+    async fn del(&mut self, offset: u64, length: u64) -> Result<(), Self::Error> {
+        if length == 0 {
+            return Ok(());
+        }
+        let end = offset + length;
+
+        // Reclaim storage for every page fully covered by the range by dropping
+        // its object; partially covered head/tail pages are zeroed in place.
+        // The logical length is left unchanged, mirroring a hole punch.
+        let mut cursor = offset;
+        while cursor < end {
+            let index = cursor / PAGE_SIZE;
+            let page_start = index * PAGE_SIZE;
+            let page_end = page_start + PAGE_SIZE;
+            let lo = std::cmp::max(offset, page_start);
+            let hi = std::cmp::min(end, page_end);
+
+            if lo == page_start && hi == page_end {
+                self.store.delete(&self.page_key(index)).await?;
+                self.cache.lock().pop(&index);
+            } else {
+                let mut page = self.load_page(index).await?;
+                for byte in page.bytes[(lo - page_start) as usize..(hi - page_start) as usize]
+                    .iter_mut()
+                {
+                    *byte = 0;
+                }
+                page.dirty = true;
+                self.cache_put(index, page.clone()).await?;
+                if self.auto_sync {
+                    self.flush_page(index, &page.bytes).await?;
+                }
+            }
+            cursor = hi;
+        }
+        Ok(())
+    }
+
+    async fn truncate(&mut self, length: u64) -> Result<(), Self::Error> {
+        let old_pages = self.length.div_ceil(PAGE_SIZE);
+        let new_pages = length.div_ceil(PAGE_SIZE);
+
+        // Drop every page object that falls entirely past the new length.
+        for index in new_pages..old_pages {
+            self.store.delete(&self.page_key(index)).await?;
+            self.cache.lock().pop(&index);
+        }
+
+        // Zero the tail of the last surviving page so stale bytes never leak.
+        let tail = (length % PAGE_SIZE) as usize;
+        if tail != 0 && length < self.length {
+            let index = length / PAGE_SIZE;
+            let mut page = self.load_page(index).await?;
+            for byte in page.bytes[tail..].iter_mut() {
+                *byte = 0;
+            }
+            page.dirty = true;
+            self.cache_put(index, page.clone()).await?;
+            self.flush_page(index, &page.bytes).await?;
+        }
+
+        self.length = length;
+        if self.auto_sync {
+            self.write_header().await?;
+        }
+        Ok(())
+    }
+
+    async fn len(&self) -> Result<u64, Self::Error> {
+        Ok(self.length)
+    }
+
+    async fn is_empty(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.length == 0)
+    }
+
+    async fn sync_all(&mut self) -> Result<(), Self::Error> {
+        // Flush every dirty page we are still holding, then the header.
+        let dirty: Vec<(u64, Vec<u8>)> = {
+            let cache = self.cache.lock();
+            cache
+                .iter()
+                .filter(|(_, page)| page.dirty)
+                .map(|(index, page)| (*index, page.bytes.clone()))
+                .collect()
+        };
+        for (index, bytes) in dirty {
+            self.flush_page(index, &bytes).await?;
+        }
+        self.write_header().await?;
+        Ok(())
+    }
+}
+
+pub struct S3Builder {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+    cache_pages: usize,
+    auto_sync: bool,
+}
+
+impl S3Builder {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: impl Into<String>) -> Self {
+        Self {
+            store,
+            prefix: prefix.into(),
+            cache_pages: DEFAULT_CACHE_PAGES,
+            auto_sync: true,
+        }
+    }
+
+    /// Number of pages to keep in the LRU read-modify-write cache.
+    pub fn cache_pages(mut self, cache_pages: usize) -> Self {
+        self.cache_pages = std::cmp::max(1, cache_pages);
+        self
+    }
+
+    pub fn auto_sync(mut self, auto_sync: bool) -> Self {
+        self.auto_sync = auto_sync;
+        self
+    }
+
+    pub async fn build(self) -> Result<RandomAccessS3, Error> {
+        let header_key = format!("{}/header", self.prefix);
+        let length = match self.store.get(&header_key).await? {
+            Some(bytes) if bytes.len() >= 8 => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(&bytes[..8]);
+                u64::from_le_bytes(buf)
+            }
+            _ => 0,
+        };
+
+        let cache = LruCache::new(
+            NonZeroUsize::new(self.cache_pages).expect("cache_pages is clamped to at least 1"),
+        );
+
+        Ok(RandomAccessS3 {
+            store: self.store,
+            prefix: self.prefix,
+            length,
+            cache: Mutex::new(cache),
+            auto_sync: self.auto_sync,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::io::Cursor;
+    use std::collections::HashMap;
+
+    /// In-memory object store for exercising the backend.
+    #[derive(Debug, Default)]
+    struct MemStore {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl ObjectStore for MemStore {
+        async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+            Ok(self.objects.lock().get(key).cloned())
+        }
+
+        async fn put(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+            self.objects.lock().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), Error> {
+            self.objects.lock().remove(key);
+            Ok(())
+        }
+    }
+
+    #[async_std::test]
+    async fn read_modify_write_across_page_boundary() {
+        let store = Arc::new(MemStore::default());
+        let mut s = RandomAccessS3::open(store, "feed").await.unwrap();
+
+        // Write a run that straddles the first page boundary.
+        let payload: Vec<u8> = (0..200u8).collect();
+        s.write(PAGE_SIZE - 50, &payload).await.unwrap();
+        assert_eq!(s.len().await.unwrap(), PAGE_SIZE - 50 + 200);
+        assert_eq!(s.read(PAGE_SIZE - 50, 200).await.unwrap(), payload);
+    }
+
+    #[async_std::test]
+    async fn dirty_page_is_flushed_when_evicted() {
+        // One-page cache with auto_sync off: writing a second page must evict
+        // and write back the first page's dirty bytes, not drop them.
+        let store = Arc::new(MemStore::default());
+        let mut s = RandomAccessS3::builder(store.clone(), "feed")
+            .cache_pages(1)
+            .auto_sync(false)
+            .build()
+            .await
+            .unwrap();
+
+        s.write(0, &[1u8; 10]).await.unwrap();
+        s.write(PAGE_SIZE, &[2u8; 10]).await.unwrap(); // evicts page 0
+        s.sync_all().await.unwrap();
+
+        // Re-open fresh so nothing is served from cache.
+        let mut reopened = RandomAccessS3::open(store, "feed").await.unwrap();
+        assert_eq!(reopened.read(0, 10).await.unwrap(), vec![1u8; 10]);
+        assert_eq!(reopened.read(PAGE_SIZE, 10).await.unwrap(), vec![2u8; 10]);
+    }
+
+    #[async_std::test]
+    async fn del_reclaims_pages_and_zeroes_without_shrinking() {
+        let store = Arc::new(MemStore::default());
+        let mut s = RandomAccessS3::open(store.clone(), "feed").await.unwrap();
+        s.write(0, &[0xAB; (PAGE_SIZE * 3) as usize]).await.unwrap();
+
+        // Delete the whole middle page plus a slice of its neighbours.
+        s.del(PAGE_SIZE - 16, PAGE_SIZE + 32).await.unwrap();
+        assert_eq!(s.len().await.unwrap(), PAGE_SIZE * 3);
+
+        // The fully covered middle page object is gone.
+        assert!(store.objects.lock().get("feed/page/1").is_none());
+
+        // Read from 16 bytes before the hole to 16 bytes past its end.
+        let out = s.read(PAGE_SIZE - 32, PAGE_SIZE + 64).await.unwrap();
+        let hole_end = (PAGE_SIZE + 48) as usize;
+        assert!(out[..16].iter().all(|b| *b == 0xAB));
+        assert!(out[16..hole_end].iter().all(|b| *b == 0));
+        assert!(out[hole_end..].iter().all(|b| *b == 0xAB));
+    }
+
+    #[async_std::test]
+    async fn read_to_writer_streams_requested_range() {
+        let store = Arc::new(MemStore::default());
+        let mut s = RandomAccessS3::open(store, "feed").await.unwrap();
+        let payload: Vec<u8> = (0..=255u8).cycle().take(300).collect();
+        s.write(0, &payload).await.unwrap();
+        let mut sink = Cursor::new(Vec::new());
+        s.read_to_writer(40, 120, &mut sink).await.unwrap();
+        assert_eq!(sink.into_inner(), payload[40..160]);
+    }
+}
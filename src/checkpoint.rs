@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::storage::Node;
+
+/// Default number of appends between checkpoint flushes.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 1;
+
+/// Fingerprint of the bitfield, used as the checkpoint's generation.
+///
+/// Any mutation of the bitfield — including ones that leave the block count
+/// unchanged — changes the fingerprint, so a stale checkpoint is detected even
+/// when the tree head has not grown.
+pub fn bitfield_generation(bytes: &[u8]) -> u64 {
+    // FNV-1a over the raw bitfield bytes.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// A snapshot of the feed head, persisted so `FeedBuilder::build` can skip the
+/// per-root `get_node` scan on cold start.
+///
+/// A fresh snapshot is flushed every N appends (see
+/// [`DEFAULT_CHECKPOINT_INTERVAL`]) so open cost stays bounded instead of
+/// scaling with the feed length.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Root nodes as of the checkpoint.
+    pub roots: Vec<Node>,
+    /// Total byte length across the roots.
+    pub byte_length: u64,
+    /// Number of blocks in the tree.
+    pub length: u64,
+    /// Bitfield generation the checkpoint was taken at, used to detect staleness.
+    pub bitfield_generation: u64,
+}
+
+impl Checkpoint {
+    pub fn new(
+        roots: Vec<Node>,
+        byte_length: u64,
+        length: u64,
+        bitfield_generation: u64,
+    ) -> Self {
+        Self {
+            roots,
+            byte_length,
+            length,
+            bitfield_generation,
+        }
+    }
+
+    /// Validate the checkpoint against the expected tree head.
+    ///
+    /// The root indices must match `flat_tree::full_roots` for the recorded
+    /// length, and the bitfield generation must line up; otherwise the
+    /// checkpoint is stale and the caller should fall back to a full scan.
+    pub fn is_valid(&self, bitfield_generation: u64) -> bool {
+        if self.bitfield_generation != bitfield_generation {
+            return false;
+        }
+        let mut expected = vec![];
+        flat_tree::full_roots((self.length * 2) as usize, &mut expected);
+        expected.len() == self.roots.len()
+            && expected
+                .iter()
+                .zip(&self.roots)
+                .all(|(idx, node)| *idx as u64 == node.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Node;
+
+    fn roots_for(length: u64) -> Vec<Node> {
+        let mut indices = vec![];
+        flat_tree::full_roots((length * 2) as usize, &mut indices);
+        indices
+            .into_iter()
+            .map(|index| Node {
+                index: index as u64,
+                hash: vec![0u8; 32],
+                length: 0,
+                parent: 0,
+                data: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_checkpoint_matches_generation_and_roots() {
+        let cp = Checkpoint::new(roots_for(4), 0, 4, 42);
+        assert!(cp.is_valid(42));
+    }
+
+    #[test]
+    fn generation_skew_is_stale() {
+        let cp = Checkpoint::new(roots_for(4), 0, 4, 42);
+        // A bitfield mutation advances the generation even at the same length.
+        assert!(!cp.is_valid(43));
+    }
+
+    #[test]
+    fn mismatched_roots_are_stale() {
+        let mut cp = Checkpoint::new(roots_for(4), 0, 4, 42);
+        cp.roots[0].index += 100;
+        assert!(!cp.is_valid(42));
+    }
+
+    #[test]
+    fn generation_changes_with_bitfield_bytes() {
+        assert_ne!(bitfield_generation(&[1, 2, 3]), bitfield_generation(&[1, 2, 4]));
+        assert_eq!(bitfield_generation(&[9, 9]), bitfield_generation(&[9, 9]));
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use random_access_storage::RandomAccess;
+use std::fmt::Debug;
+
+use crate::checkpoint::{bitfield_generation, Checkpoint};
+use crate::Feed;
+
+impl<T> Feed<T>
+where
+    T: RandomAccess<Error = Box<dyn std::error::Error + Send + Sync>> + Debug + Send,
+{
+    /// Write a fresh checkpoint from the current feed head.
+    ///
+    /// Captures the live root nodes, `byte_length`, `length` and the current
+    /// bitfield generation so the next `FeedBuilder::build` can skip the
+    /// per-root scan. Resets the append counter.
+    pub async fn flush_checkpoint(&mut self) -> Result<()> {
+        let roots = self.merkle.roots().to_vec();
+        let generation = self
+            .storage
+            .read_bitfield()
+            .await
+            .map(|bytes| bitfield_generation(&bytes))
+            .unwrap_or_else(|_| bitfield_generation(&[]));
+
+        let checkpoint = Checkpoint::new(
+            roots,
+            self.byte_length as u64,
+            self.length as u64,
+            generation,
+        );
+        self.storage.write_checkpoint(&checkpoint).await?;
+        self.appends_since_checkpoint = 0;
+        Ok(())
+    }
+
+    /// Flush a checkpoint once `checkpoint_interval` appends have accumulated.
+    ///
+    /// Called from the append path; with an interval of `1` every append
+    /// refreshes the checkpoint, so a reopen after any append still hits the
+    /// O(1) path instead of falling back to the full root scan.
+    pub(crate) async fn maybe_flush_checkpoint(&mut self) -> Result<()> {
+        self.appends_since_checkpoint += 1;
+        if self.checkpoint_interval != 0
+            && self.appends_since_checkpoint >= self.checkpoint_interval
+        {
+            self.flush_checkpoint().await?;
+        }
+        Ok(())
+    }
+}
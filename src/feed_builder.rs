@@ -1,6 +1,12 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::Aes256Gcm;
+use argon2::Argon2;
+use chacha20poly1305::ChaCha20Poly1305;
 use ed25519_dalek::{PublicKey, SecretKey};
+use rand::RngCore;
 
 use crate::bitfield::Bitfield;
+use crate::checkpoint::Checkpoint;
 use crate::crypto::Merkle;
 use crate::storage::Storage;
 use random_access_storage::RandomAccess;
@@ -8,7 +14,19 @@ use std::fmt::Debug;
 use tree_index::TreeIndex;
 
 use crate::Feed;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+
+const SECRET_KEY_SALT_LEN: usize = 16;
+const SECRET_KEY_NONCE_LEN: usize = 12;
+
+/// AEAD used to wrap the feed's Ed25519 secret key at rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretKeyCipher {
+    /// AES-256 in Galois/Counter mode.
+    Aes256Gcm,
+    /// ChaCha20-Poly1305.
+    ChaCha20Poly1305,
+}
 
 /// Construct a new `Feed` instance.
 // TODO: make this an actual builder pattern.
@@ -21,6 +39,9 @@ where
     storage: Storage<T>,
     public_key: PublicKey,
     secret_key: Option<SecretKey>,
+    passphrase: Option<String>,
+    cipher: SecretKeyCipher,
+    checkpoint_interval: u64,
 }
 
 impl<T> FeedBuilder<T>
@@ -34,15 +55,40 @@ where
             storage,
             public_key,
             secret_key: None,
+            passphrase: None,
+            cipher: SecretKeyCipher::ChaCha20Poly1305,
+            checkpoint_interval: crate::checkpoint::DEFAULT_CHECKPOINT_INTERVAL,
         }
     }
 
+    /// Number of appends between checkpoint flushes (see [`crate::checkpoint`]).
+    pub fn checkpoint_interval(mut self, interval: u64) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
     /// Set the secret key.
     pub fn secret_key(mut self, secret_key: SecretKey) -> Self {
         self.secret_key = Some(secret_key);
         self
     }
 
+    /// Encrypt the secret key at rest with a key derived from `passphrase`.
+    ///
+    /// When set, the secret-key record becomes `salt || nonce || ciphertext
+    /// || tag` instead of the raw Ed25519 bytes. Leaving it unset keeps the
+    /// plaintext record for backward compatibility.
+    pub fn passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// Select the AEAD used to wrap the secret key under [`Self::passphrase`].
+    pub fn cipher(mut self, cipher: SecretKeyCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
     /// Finalize the builder.
     #[inline]
     pub async fn build(mut self) -> Result<Feed<T>> {
@@ -51,43 +97,73 @@ where
 
         let mut secret_key: Option<SecretKey> = None;
 
-        if self.secret_key.is_some() {
-            let unwrapped_secret_key = self.secret_key.unwrap();
-            secret_key = Some(SecretKey::from_bytes(unwrapped_secret_key.as_bytes())?);
-
-            self.storage
-                .write_secret_key(&unwrapped_secret_key)
-                .await?;
+        match (&self.secret_key, &self.passphrase) {
+            // Fresh key with a passphrase: wrap it and persist the sealed record.
+            (Some(unwrapped_secret_key), Some(passphrase)) => {
+                secret_key = Some(SecretKey::from_bytes(unwrapped_secret_key.as_bytes())?);
+                let record = seal_secret_key(self.cipher, passphrase, unwrapped_secret_key)?;
+                self.storage.write_secret_key_record(&record).await?;
+            }
+            // Fresh key, no passphrase: keep the legacy plaintext path.
+            (Some(unwrapped_secret_key), None) => {
+                secret_key = Some(SecretKey::from_bytes(unwrapped_secret_key.as_bytes())?);
+                self.storage.write_secret_key(unwrapped_secret_key).await?;
+            }
+            // Re-open with a passphrase: read the sealed record and unseal it.
+            (None, Some(passphrase)) => {
+                if let Ok(record) = self.storage.read_secret_key_record().await {
+                    secret_key = Some(unseal_secret_key(self.cipher, passphrase, &record)?);
+                }
+            }
+            (None, None) => {}
         }
 
-        let (bitfield, tree) = if let Ok(bitfield) = self.storage.read_bitfield().await {
-            Bitfield::from_slice(&bitfield)
-        } else {
-            Bitfield::new()
+        let raw_bitfield = self.storage.read_bitfield().await.ok();
+        let (bitfield, tree) = match &raw_bitfield {
+            Some(bytes) => Bitfield::from_slice(bytes),
+            None => Bitfield::new(),
         };
         use crate::storage::Node;
 
-        let mut tree = TreeIndex::new(tree);
-        let mut roots = vec![];
-        flat_tree::full_roots(tree.blocks() * 2, &mut roots);
-        let mut result: Vec<Option<Node>> = vec![None; roots.len()];
-
-        for i in 0..roots.len() {
-            let node = self.storage.get_node(roots[i] as u64).await?;
-            let idx = roots
-                .iter()
-                .position(|&x| x == node.index)
-                .ok_or_else(|| anyhow::anyhow!("Couldnt find idx of node"))?;
-            result[idx] = Some(node);
-        }
+        let tree = TreeIndex::new(tree);
+        // The checkpoint generation is a fingerprint of the bitfield bytes, so
+        // any bitfield mutation — even one that leaves the block count
+        // unchanged — invalidates a stale checkpoint.
+        let generation =
+            crate::checkpoint::bitfield_generation(raw_bitfield.as_deref().unwrap_or(&[]));
+
+        // Prefer the O(1) checkpoint; fall back to the full root scan only if
+        // it is missing or stale.
+        let roots = match self.storage.read_checkpoint().await {
+            Ok(checkpoint) if checkpoint.is_valid(generation) => checkpoint.roots,
+            _ => {
+                let mut root_indices = vec![];
+                flat_tree::full_roots(tree.blocks() * 2, &mut root_indices);
+                let mut result: Vec<Option<Node>> = vec![None; root_indices.len()];
+
+                for i in 0..root_indices.len() {
+                    let node = self.storage.get_node(root_indices[i] as u64).await?;
+                    let idx = root_indices
+                        .iter()
+                        .position(|&x| x == node.index)
+                        .ok_or_else(|| anyhow::anyhow!("Couldnt find idx of node"))?;
+                    result[idx] = Some(node);
+                }
 
-        let roots = result
-            .into_iter()
-            .collect::<Option<Vec<_>>>()
-            .ok_or_else(|| anyhow::anyhow!("Roots contains undefined nodes"))?;
+                result
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| anyhow::anyhow!("Roots contains undefined nodes"))?
+            }
+        };
 
         let byte_length = roots.iter().fold(0, |acc, node| acc + node.length);
 
+        // Refresh the checkpoint so the next cold start stays O(1).
+        let checkpoint =
+            Checkpoint::new(roots.clone(), byte_length as u64, tree.blocks() as u64, generation);
+        let _ = self.storage.write_checkpoint(&checkpoint).await;
+
         Ok(Feed {
             merkle: Merkle::from_nodes(roots),
             byte_length,
@@ -98,6 +174,123 @@ where
             secret_key: secret_key,
             storage: self.storage,
             peers: vec![],
+            checkpoint_interval: self.checkpoint_interval,
+            appends_since_checkpoint: 0,
         })
     }
 }
+
+/// Derive a 32-byte wrapping key from `passphrase` and `salt` with Argon2id.
+fn derive_wrapping_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Seal the secret key into a `salt || nonce || ciphertext || tag` record.
+fn seal_secret_key(
+    cipher: SecretKeyCipher,
+    passphrase: &str,
+    secret_key: &SecretKey,
+) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SECRET_KEY_SALT_LEN];
+    let mut nonce = [0u8; SECRET_KEY_NONCE_LEN];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let key = derive_wrapping_key(passphrase, &salt)?;
+    let payload = Payload {
+        msg: secret_key.as_bytes(),
+        aad: &[],
+    };
+    let sealed = match cipher {
+        SecretKeyCipher::Aes256Gcm => Aes256Gcm::new(key.as_ref().into())
+            .encrypt(nonce.as_ref().into(), payload)
+            .map_err(|_| anyhow!("secret key seal failed"))?,
+        SecretKeyCipher::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt(nonce.as_ref().into(), payload)
+            .map_err(|_| anyhow!("secret key seal failed"))?,
+    };
+
+    let mut record = Vec::with_capacity(salt.len() + nonce.len() + sealed.len());
+    record.extend_from_slice(&salt);
+    record.extend_from_slice(&nonce);
+    record.extend_from_slice(&sealed);
+    Ok(record)
+}
+
+/// Unseal a secret-key record, failing cleanly on a wrong passphrase.
+fn unseal_secret_key(
+    cipher: SecretKeyCipher,
+    passphrase: &str,
+    record: &[u8],
+) -> Result<SecretKey> {
+    let header = SECRET_KEY_SALT_LEN + SECRET_KEY_NONCE_LEN;
+    if record.len() <= header {
+        return Err(anyhow!("secret key record is truncated"));
+    }
+    let salt = &record[..SECRET_KEY_SALT_LEN];
+    let nonce = &record[SECRET_KEY_SALT_LEN..header];
+    let sealed = &record[header..];
+
+    let key = derive_wrapping_key(passphrase, salt)?;
+    let payload = Payload {
+        msg: sealed,
+        aad: &[],
+    };
+    // A tag mismatch means the derived key is wrong — almost always a bad
+    // passphrase. Surface that distinctly rather than returning garbage bytes.
+    let plaintext = match cipher {
+        SecretKeyCipher::Aes256Gcm => Aes256Gcm::new(key.as_ref().into())
+            .decrypt(nonce.into(), payload),
+        SecretKeyCipher::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(nonce.into(), payload),
+    }
+    .map_err(|_| anyhow!("wrong passphrase: secret key authentication failed"))?;
+
+    Ok(SecretKey::from_bytes(&plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn sample_secret_key() -> SecretKey {
+        let keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        SecretKey::from_bytes(keypair.secret.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn secret_key_seal_round_trips_for_both_ciphers() {
+        for cipher in [
+            SecretKeyCipher::Aes256Gcm,
+            SecretKeyCipher::ChaCha20Poly1305,
+        ] {
+            let secret = sample_secret_key();
+            let record = seal_secret_key(cipher, "correct horse", &secret).unwrap();
+            // The record is the salt/nonce header plus sealed bytes, never the
+            // plaintext key.
+            assert_ne!(&record[SECRET_KEY_SALT_LEN + SECRET_KEY_NONCE_LEN..], secret.as_bytes());
+
+            let opened = unseal_secret_key(cipher, "correct horse", &record).unwrap();
+            assert_eq!(opened.as_bytes(), secret.as_bytes());
+        }
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let secret = sample_secret_key();
+        let record = seal_secret_key(SecretKeyCipher::ChaCha20Poly1305, "right", &secret).unwrap();
+        let err = unseal_secret_key(SecretKeyCipher::ChaCha20Poly1305, "wrong", &record);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn truncated_record_is_rejected() {
+        assert!(unseal_secret_key(SecretKeyCipher::Aes256Gcm, "x", &[0u8; 4]).is_err());
+    }
+}
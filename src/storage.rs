@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use ed25519_dalek::{PublicKey, SecretKey, PUBLIC_KEY_LENGTH};
+use random_access_storage::RandomAccess;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::checkpoint::Checkpoint;
+
+/// On-disk size of a single tree node record: `length || hash`.
+const NODE_SIZE: u64 = 40;
+
+/// A node in the merkle tree, persisted in the `tree` partition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Node {
+    /// Flat-tree index of the node.
+    pub index: u64,
+    /// Hash of the node's subtree.
+    pub hash: Vec<u8>,
+    /// Number of bytes the node covers.
+    pub length: u64,
+    /// Flat-tree index of the parent.
+    pub parent: u64,
+    /// Block data, present only for leaf nodes that are cached in memory.
+    pub data: Option<Vec<u8>>,
+}
+
+impl Node {
+    /// Encode the fixed portion of the node (`length || hash`).
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(NODE_SIZE as usize);
+        buf.extend_from_slice(&self.length.to_be_bytes());
+        buf.extend_from_slice(&self.hash);
+        buf.resize(NODE_SIZE as usize, 0);
+        buf
+    }
+
+    /// Decode a node record stored at flat-tree `index`.
+    fn from_bytes(index: u64, buf: &[u8]) -> Result<Self> {
+        if buf.len() < NODE_SIZE as usize {
+            return Err(anyhow!("node record at {} is truncated", index));
+        }
+        let mut len_buf = [0u8; 8];
+        len_buf.copy_from_slice(&buf[..8]);
+        Ok(Node {
+            index,
+            hash: buf[8..NODE_SIZE as usize].to_vec(),
+            length: u64::from_be_bytes(len_buf),
+            parent: flat_tree::parent(index),
+            data: None,
+        })
+    }
+}
+
+/// Partitioned storage for a single feed.
+///
+/// Each logical region lives in its own `RandomAccess` partition so writes to
+/// the tree, data, bitfield and keypair never collide on a byte range.
+#[derive(Debug)]
+pub struct Storage<T>
+where
+    T: RandomAccess + Debug,
+{
+    tree: T,
+    data: T,
+    bitfield: T,
+    keypair: T,
+    /// Sealed secret-key record (`salt || nonce || ciphertext || tag`).
+    secret_key_record: T,
+    /// Serialized cold-start [`Checkpoint`].
+    checkpoint: T,
+}
+
+impl<T> Storage<T>
+where
+    T: RandomAccess<Error = Box<dyn std::error::Error + Send + Sync>> + Debug + Send,
+{
+    /// Assemble a `Storage` from its partitions.
+    pub fn new(
+        tree: T,
+        data: T,
+        bitfield: T,
+        keypair: T,
+        secret_key_record: T,
+        checkpoint: T,
+    ) -> Self {
+        Self {
+            tree,
+            data,
+            bitfield,
+            keypair,
+            secret_key_record,
+            checkpoint,
+        }
+    }
+
+    /// Persist the feed public key.
+    pub async fn write_public_key(&mut self, public_key: &PublicKey) -> Result<()> {
+        self.keypair
+            .write(0, public_key.as_bytes())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Persist the raw (plaintext) secret key.
+    pub async fn write_secret_key(&mut self, secret_key: &SecretKey) -> Result<()> {
+        self.keypair
+            .write(PUBLIC_KEY_LENGTH as u64, secret_key.as_bytes())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Persist the sealed secret-key record, length-prefixed so it round-trips.
+    pub async fn write_secret_key_record(&mut self, record: &[u8]) -> Result<()> {
+        let mut framed = (record.len() as u64).to_be_bytes().to_vec();
+        framed.extend_from_slice(record);
+        self.secret_key_record
+            .write(0, &framed)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Read the sealed secret-key record written by [`Self::write_secret_key_record`].
+    pub async fn read_secret_key_record(&mut self) -> Result<Vec<u8>> {
+        let len = self.secret_key_record.len().await.map_err(|e| anyhow!(e))?;
+        if len < 8 {
+            return Err(anyhow!("no secret key record present"));
+        }
+        let header = self.secret_key_record.read(0, 8).await.map_err(|e| anyhow!(e))?;
+        let mut len_buf = [0u8; 8];
+        len_buf.copy_from_slice(&header);
+        let record_len = u64::from_be_bytes(len_buf);
+        self.secret_key_record
+            .read(8, record_len)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Persist a fresh cold-start checkpoint.
+    pub async fn write_checkpoint(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        let bytes = bincode::serialize(checkpoint)?;
+        let mut framed = (bytes.len() as u64).to_be_bytes().to_vec();
+        framed.extend_from_slice(&bytes);
+        self.checkpoint.write(0, &framed).await.map_err(|e| anyhow!(e))
+    }
+
+    /// Load the cold-start checkpoint, if one has been written.
+    pub async fn read_checkpoint(&mut self) -> Result<Checkpoint> {
+        let len = self.checkpoint.len().await.map_err(|e| anyhow!(e))?;
+        if len < 8 {
+            return Err(anyhow!("no checkpoint present"));
+        }
+        let header = self.checkpoint.read(0, 8).await.map_err(|e| anyhow!(e))?;
+        let mut len_buf = [0u8; 8];
+        len_buf.copy_from_slice(&header);
+        let record_len = u64::from_be_bytes(len_buf);
+        let bytes = self
+            .checkpoint
+            .read(8, record_len)
+            .await
+            .map_err(|e| anyhow!(e))?;
+        Ok(bincode::deserialize(&bytes)?)
+    }
+
+    /// Read the raw bitfield bytes.
+    pub async fn read_bitfield(&mut self) -> Result<Vec<u8>> {
+        let len = self.bitfield.len().await.map_err(|e| anyhow!(e))?;
+        self.bitfield.read(0, len).await.map_err(|e| anyhow!(e))
+    }
+
+    /// Read a tree node by flat-tree index.
+    pub async fn get_node(&mut self, index: u64) -> Result<Node> {
+        let buf = self
+            .tree
+            .read(index * NODE_SIZE, NODE_SIZE)
+            .await
+            .map_err(|e| anyhow!(e))?;
+        Node::from_bytes(index, &buf)
+    }
+
+    /// Write a tree node at its flat-tree index.
+    pub async fn put_node(&mut self, node: &Node) -> Result<()> {
+        self.tree
+            .write(node.index * NODE_SIZE, &node.to_bytes())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Append block `data` to the data partition at `offset`.
+    pub async fn write_data(&mut self, offset: u64, data: &[u8]) -> Result<()> {
+        self.data.write(offset, data).await.map_err(|e| anyhow!(e))
+    }
+}